@@ -1,3 +1,5 @@
+pub mod triple;
+
 /// Linux-Based Operating System
 #[derive(Copy, Clone, Debug)]
 pub enum LinuxKernel {
@@ -15,6 +17,9 @@ pub enum DarwinKernel {
     IPadOs,
     WatchOS,
     TVOS,
+    VisionOS,
+    /// An iOS app running on macOS via Mac Catalyst (`target_os = "ios"` + `target_abi = "macabi"`)
+    MacCatalyst,
 }
 
 /// An operating system that is in some way "Unix-like"
@@ -27,13 +32,20 @@ pub enum UnixLike {
     SolarisOrUhOopsIMeanIllumos,
 }
 
+/// Which CRT/ABI a Windows build is targeting
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WindowsAbi {
+    Msvc,
+    Gnu,
+}
+
 /// An operating system that runs off of the NT kernel
 /// WARNING! This will not detect Windows Servers yet, so don't add cases for `NTKernel::WindowsServer`
 /// until i stop being lazy and add support for it
 #[derive(Copy, Clone, Debug)]
 pub enum NTKernel {
-    Windows,
-    WindowsServer,
+    Windows(WindowsAbi),
+    WindowsServer(WindowsAbi),
 }
 
 /// Operating Systems are what makes your computer do things without you having to manually connect pins on your CPU
@@ -48,7 +60,7 @@ pub enum OperatingSystem {
 /// The x86 Architecture
 /// WARNING! I don't think this will actually properly detect 8086 cpus yet
 /// so you might not want to use this crate for DOS programming until i fix this
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum X86 {
     AMD64,
     I386,
@@ -61,30 +73,87 @@ pub enum X86 {
 // todo! research into what all of this actually means
 /// The "Arm" Architecture
 /// I don't actually know much about this one, so it may get incorrect results!
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum ARM {
     AArch32,
     AArch64,
     AppleSilicon,
 }
 
+/// Which way round a MIPS CPU's bytes go. Linux reports `mips64` for both endiannesses,
+/// so this has to come from `target_endian`, not the arch string.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Endianness {
+    Big,
+    Little,
+}
+
+/// Detects the endianness of the running CPU.
+pub fn detect_endianness() -> Endianness {
+    if cfg!(target_endian = "big") {
+        Endianness::Big
+    } else {
+        Endianness::Little
+    }
+}
+
+/// The MIPS calling convention/ABI in use. Only meaningful for the 32/64-bit variants,
+/// since the older revisions predate this distinction.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MipsAbi {
+    O32,
+    N32,
+    N64,
+}
+
+/// Works out the ABI for a 64-bit MIPS CPU. Defaults to n64, except when the pointer width
+/// says we're actually running the n32 ABI (64-bit MIPS instructions, 32-bit pointers).
+fn detect_mips64_abi() -> MipsAbi {
+    if cfg!(target_pointer_width = "32") {
+        MipsAbi::N32
+    } else {
+        MipsAbi::N64
+    }
+}
+
+/// Is this a 32-bit MIPS CPU?
+pub fn is_mips32(cpu: MIPS) -> bool {
+    matches!(cpu, MIPS::Mips32 { .. })
+}
+
+/// Is this a 64-bit MIPS CPU?
+pub fn is_mips64(cpu: MIPS) -> bool {
+    matches!(cpu, MIPS::Mips64 { .. })
+}
+
+/// Is this a 64-bit MIPS CPU running the n32 ABI (64-bit instructions, 32-bit pointers)?
+pub fn is_mips64_n32(cpu: MIPS) -> bool {
+    matches!(
+        cpu,
+        MIPS::Mips64 {
+            abi: MipsAbi::N32,
+            ..
+        }
+    )
+}
+
 // i love mips so much 🥵
 /// The Best or uh I mean The MIPS Architecture
 /// WARNING! this doesn't actually detect MIPSI, MIPSII, MIPSIII, or MIPSIV yet
 /// you can only detect MIPS32 or MIPS64
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum MIPS {
     MipsI,
     MipsII,
     MipsIII,
     MipsIV,
     MipsV,
-    Mips32,
-    Mips64,
+    Mips32 { abi: MipsAbi, endian: Endianness },
+    Mips64 { abi: MipsAbi, endian: Endianness },
 }
 
 /// CPUs make your computer a "computer"
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum CPU {
     X86(X86),
     ARM(ARM),
@@ -104,6 +173,90 @@ pub enum CPU {
     Other,
 }
 
+/// Which C library the program is linked against.
+/// This is mostly derived from the `OperatingSystem`, since on most platforms the libc
+/// is a foregone conclusion once you know the OS (and, on Linux, the kernel variant).
+#[derive(Copy, Clone, Debug)]
+pub enum Libc {
+    Glibc,
+    Musl,
+    LibSystem,
+    Msvcrt,
+    WasiLibc,
+    Bionic,
+    Unknown,
+}
+
+/// Detects which C library the program is linked against, derived from `detect_os()`
+pub fn detect_libc() -> Libc {
+    match detect_os() {
+        OperatingSystem::UnixLike(UnixLike::Linux(LinuxKernel::NormalLinuxGnu)) => Libc::Glibc,
+        OperatingSystem::UnixLike(UnixLike::Linux(LinuxKernel::NormalLinuxMusl)) => Libc::Musl,
+        OperatingSystem::UnixLike(UnixLike::Linux(LinuxKernel::ChromeOS)) => Libc::Glibc,
+        OperatingSystem::UnixLike(UnixLike::Linux(LinuxKernel::Android)) => Libc::Bionic,
+        OperatingSystem::UnixLike(UnixLike::Darwin(_)) => Libc::LibSystem,
+        OperatingSystem::Windows(_) => {
+            // the MSVC toolchain links against the Universal CRT, not classic msvcrt.dll,
+            // so there's no real "msvcrt" to report there
+            if cfg!(target_env = "msvc") {
+                Libc::Unknown
+            } else {
+                Libc::Msvcrt
+            }
+        }
+        // todo! wire up `WasiLibc` once we have a WASI `OperatingSystem` variant to detect
+        _ => Libc::Unknown,
+    }
+}
+
+/// The actual word size of the running process, as opposed to whatever
+/// `std::env::consts::ARCH` was compiled for
+#[derive(Copy, Clone, Debug)]
+pub enum Bitness {
+    ThirtyTwo,
+    SixtyFour,
+}
+
+/// Reads the ELF header of `/proc/self/exe` to get the real bitness, instead of trusting
+/// the compile-time constants (which lie under e.g. QEMU user-mode emulation).
+/// `None` if `/proc` isn't mounted or the file isn't ELF.
+pub fn detect_bitness_runtime() -> Option<Bitness> {
+    use std::io::Read;
+
+    let mut header = [0u8; 5];
+    let mut exe = std::fs::File::open("/proc/self/exe").ok()?;
+    exe.read_exact(&mut header).ok()?;
+
+    if header[0..4] != [0x7f, b'E', b'L', b'F'] {
+        return None;
+    }
+
+    match header[4] {
+        0x01 => Some(Bitness::ThirtyTwo),
+        0x02 => Some(Bitness::SixtyFour),
+        _ => None,
+    }
+}
+
+/// Downgrades a compile-time detected `CPU` (e.g. `X86::AMD64`) to its 32-bit variant if
+/// we're actually running 32-bit. Trusts the compiled value if we can't tell either way.
+pub fn reconcile_bitness(compiled: CPU) -> CPU {
+    let runtime_bitness = match detect_bitness_runtime() {
+        Some(bitness) => bitness,
+        None => return compiled,
+    };
+
+    match (compiled, runtime_bitness) {
+        (CPU::X86(X86::AMD64), Bitness::ThirtyTwo) => CPU::X86(X86::I686),
+        (CPU::ARM(ARM::AArch64), Bitness::ThirtyTwo) => CPU::ARM(ARM::AArch32),
+        (CPU::MIPS(MIPS::Mips64 { endian, .. }), Bitness::ThirtyTwo) => CPU::MIPS(MIPS::Mips32 {
+            abi: MipsAbi::O32,
+            endian,
+        }),
+        _ => compiled,
+    }
+}
+
 /// Window Systems let you see things on your screen that aren't just text
 /// I mean I guess there are also text based window systems but we aren't detecting those yet
 #[derive(Copy, Clone, Debug)]
@@ -118,6 +271,31 @@ pub enum WindowSystem {
 
 /// Detects what operating system the program is running on, and returns the `OperatingSystem` enum
 pub fn detect_os() -> OperatingSystem {
+    // `std::env::consts::OS` only ever reports "macos", so checking it directly can't tell
+    // iOS/tvOS/watchOS/visionOS apart (and can't see Mac Catalyst at all). `target_vendor`
+    // is the umbrella Apple's own toolchain uses, so key off that first and disambiguate
+    // with `target_os`/`target_abi` underneath.
+    if cfg!(target_vendor = "apple") {
+        let kernel = if cfg!(target_os = "ios") && cfg!(target_abi = "macabi") {
+            DarwinKernel::MacCatalyst
+        } else if cfg!(target_os = "ios") {
+            DarwinKernel::IOS
+        } else if cfg!(target_os = "ipados") {
+            DarwinKernel::IPadOs
+        } else if cfg!(target_os = "watchos") {
+            DarwinKernel::WatchOS
+        } else if cfg!(target_os = "tvos") {
+            DarwinKernel::TVOS
+        } else if cfg!(target_os = "visionos") {
+            DarwinKernel::VisionOS
+        } else {
+            // todo! figure out what to do here
+            DarwinKernel::MacOSGreaterThan9
+        };
+
+        return OperatingSystem::UnixLike(UnixLike::Darwin(kernel));
+    }
+
     match std::env::consts::OS {
         "linux" => {
             // figure out whether we're on gnu or musl
@@ -132,35 +310,21 @@ pub fn detect_os() -> OperatingSystem {
 
             OperatingSystem::UnixLike(UnixLike::Linux(kernel))
         }
-        "macos" => {
-            // figure out whether we're on macos or ios
-            let kernel = if cfg!(target_os = "ios") {
-                DarwinKernel::IOS
-            } else if cfg!(target_os = "macos") {
-                DarwinKernel::MacOSGreaterThan9
-            } else if cfg!(target_os = "ipados") {
-                DarwinKernel::IPadOs
-            } else if cfg!(target_os = "watchos") {
-                DarwinKernel::WatchOS
-            } else if cfg!(target_os = "tvos") {
-                DarwinKernel::TVOS
-            } else {
-                // todo! figure out what to do here
-                DarwinKernel::MacOSGreaterThan9
-            };
-
-            OperatingSystem::UnixLike(UnixLike::Darwin(kernel))
-        }
         "windows" => {
             // todo! check if we're on windows server
 
-            OperatingSystem::Windows(NTKernel::Windows)
+            let abi = if cfg!(target_env = "gnu") {
+                WindowsAbi::Gnu
+            } else {
+                WindowsAbi::Msvc
+            };
+
+            OperatingSystem::Windows(NTKernel::Windows(abi))
         }
         "freebsd" | "netbsd" | "openbsd" | "dragonfly" => OperatingSystem::UnixLike(UnixLike::BSD),
         "solaris" | "illumos" => OperatingSystem::UnixLike(UnixLike::SolarisOrUhOopsIMeanIllumos),
         "dos" => OperatingSystem::DOS,
         "android" => OperatingSystem::UnixLike(UnixLike::Linux(LinuxKernel::Android)),
-        "ios" => OperatingSystem::UnixLike(UnixLike::Darwin(DarwinKernel::IOS)),
         _ => OperatingSystem::Unknown,
     }
 }
@@ -176,8 +340,14 @@ pub fn detect_architecture() -> CPU {
         "8086" => CPU::X86(X86::EightyEightySix),
         "arm" => CPU::ARM(ARM::AArch32),
         "aarch64" => CPU::ARM(ARM::AArch64),
-        "mips" => CPU::MIPS(MIPS::Mips32),
-        "mips64" => CPU::MIPS(MIPS::Mips64),
+        "mips" => CPU::MIPS(MIPS::Mips32 {
+            abi: MipsAbi::O32,
+            endian: detect_endianness(),
+        }),
+        "mips64" => CPU::MIPS(MIPS::Mips64 {
+            abi: detect_mips64_abi(),
+            endian: detect_endianness(),
+        }),
         "powerpc" => CPU::PowerPC,
         "sparc" => CPU::SPARC,
         "risc" => CPU::RISC,
@@ -194,6 +364,172 @@ pub fn detect_architecture() -> CPU {
     }
 }
 
+/// Ranks the 32-bit `X86` variants from oldest to newest, so we can tell whether code built
+/// for one can run on another. Returns `None` for `X86::AMD64`, which isn't part of this
+/// 32-bit chain.
+fn x86_rank(cpu: X86) -> Option<u8> {
+    match cpu {
+        X86::EightyEightySix => Some(0),
+        X86::I386 => Some(1),
+        X86::I486 => Some(2),
+        X86::I586 => Some(3),
+        X86::I686 => Some(4),
+        X86::AMD64 => None,
+    }
+}
+
+/// Ranks the `MIPS` variants along the I → II → III → IV → V → 32/64-bit chain, so 32-bit
+/// code can be recognized as running on a 64-bit host.
+fn mips_rank(cpu: MIPS) -> u8 {
+    match cpu {
+        MIPS::MipsI => 0,
+        MIPS::MipsII => 1,
+        MIPS::MipsIII => 2,
+        MIPS::MipsIV => 3,
+        MIPS::MipsV => 4,
+        MIPS::Mips32 { .. } => 5,
+        MIPS::Mips64 { .. } => 6,
+    }
+}
+
+/// Pulls the `Endianness` out of a MIPS variant that carries one. The legacy `MipsI..MipsV`
+/// variants don't track endianness at all, so they return `None`.
+fn mips_endianness(cpu: MIPS) -> Option<Endianness> {
+    match cpu {
+        MIPS::Mips32 { endian, .. } | MIPS::Mips64 { endian, .. } => Some(endian),
+        _ => None,
+    }
+}
+
+/// Answers whether a binary built for `compiled` can actually execute on a `host` CPU,
+/// following the usual superset relationships (32-bit x86 on amd64, AArch32 on AArch64,
+/// older MIPS revisions on newer ones, and so on). Cross-family pairs (e.g. ARM vs X86)
+/// are never compatible, and every CPU is trivially compatible with itself.
+pub fn is_compatible(compiled: CPU, host: CPU) -> bool {
+    if compiled == host {
+        return true;
+    }
+
+    match (compiled, host) {
+        (CPU::X86(compiled), CPU::X86(X86::AMD64)) => compiled != X86::AMD64,
+        (CPU::X86(compiled), CPU::X86(host)) => match (x86_rank(compiled), x86_rank(host)) {
+            (Some(compiled_rank), Some(host_rank)) => compiled_rank <= host_rank,
+            _ => false,
+        },
+        (CPU::ARM(ARM::AArch32), CPU::ARM(ARM::AArch64)) => true,
+        (CPU::ARM(ARM::AArch32), CPU::ARM(ARM::AppleSilicon)) => true,
+        (CPU::MIPS(compiled), CPU::MIPS(host)) => {
+            // a little-endian binary can't execute on a big-endian host (and vice versa),
+            // regardless of how the 32/64-bit ranks compare
+            match (mips_endianness(compiled), mips_endianness(host)) {
+                (Some(compiled_endian), Some(host_endian)) if compiled_endian != host_endian => {
+                    false
+                }
+                _ => mips_rank(compiled) <= mips_rank(host),
+            }
+        }
+        _ => false,
+    }
+}
+
+/// Shells out to `uname -s -m -p` and splits the result into (sysname, machine, processor).
+/// Returns `None` if `uname` isn't available (e.g. on Windows) or didn't print anything useful.
+fn run_uname() -> Option<(String, String, String)> {
+    let output = std::process::Command::new("uname")
+        .arg("-s")
+        .arg("-m")
+        .arg("-p")
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let stdout = String::from_utf8(output.stdout).ok()?;
+    let fields: Vec<&str> = stdout.split_whitespace().collect();
+
+    match fields.as_slice() {
+        [sysname, machine, processor] => Some((
+            sysname.to_string(),
+            machine.to_string(),
+            processor.to_string(),
+        )),
+        _ => None,
+    }
+}
+
+/// Detects what operating system the program is *actually* running on by shelling out to
+/// `uname -s`, rather than trusting the compile-time constants (relevant when a binary gets
+/// copied to a different machine, or run under an emulation/compatibility layer).
+/// Falls back to `detect_os()` if `uname` isn't available or doesn't return anything useful.
+pub fn detect_os_runtime() -> OperatingSystem {
+    let Some((sysname, ..)) = run_uname() else {
+        return detect_os();
+    };
+
+    match sysname.as_str() {
+        // uname alone can't tell gnu/musl/android/ChromeOS apart, so this is our best guess
+        "Linux" => OperatingSystem::UnixLike(UnixLike::Linux(LinuxKernel::NormalLinuxGnu)),
+        "Darwin" => OperatingSystem::UnixLike(UnixLike::Darwin(DarwinKernel::MacOSGreaterThan9)),
+        "FreeBSD" | "NetBSD" | "OpenBSD" | "DragonFly" => OperatingSystem::UnixLike(UnixLike::BSD),
+        "SunOS" => OperatingSystem::UnixLike(UnixLike::SolarisOrUhOopsIMeanIllumos),
+        _ => OperatingSystem::Unknown,
+    }
+}
+
+/// Maps a `uname -p` processor string to a 32-bit `X86` variant, when it names one.
+/// Some platforms always report a generic `i686` for `-m` regardless of the actual
+/// generation, so `-p` is the only field that can tell them apart.
+fn x86_variant_from_processor(processor: &str) -> Option<X86> {
+    match processor {
+        "i386" => Some(X86::I386),
+        "i486" => Some(X86::I486),
+        "i586" => Some(X86::I586),
+        "i686" => Some(X86::I686),
+        _ => None,
+    }
+}
+
+/// Detects the real host architecture via `uname -m`, falling back to `detect_architecture()`
+/// if `uname` isn't available.
+pub fn detect_architecture_runtime() -> CPU {
+    let Some((_sysname, machine, processor)) = run_uname() else {
+        return detect_architecture();
+    };
+
+    match machine.as_str() {
+        "x86_64" | "amd64" => CPU::X86(X86::AMD64),
+        "i386" | "i486" | "i586" | "i686" => {
+            // `-p` is often just "unknown" (e.g. on Linux), so only let it override `-m`
+            // when it actually names a variant - otherwise trust what `-m` said
+            let from_machine = x86_variant_from_processor(&machine).unwrap_or(X86::I686);
+            CPU::X86(x86_variant_from_processor(&processor).unwrap_or(from_machine))
+        }
+        // Darwin reports "arm64" for Apple Silicon; Linux/others report "aarch64" for
+        // everything else, so the machine string alone is enough to tell them apart here
+        "arm64" => CPU::ARM(ARM::AppleSilicon),
+        "aarch64" => CPU::ARM(ARM::AArch64),
+        "arm" | "armv6l" | "armv7l" => CPU::ARM(ARM::AArch32),
+        // known gap: `uname` can't tell us the *host's* endianness/ABI here (it reports
+        // "mips64" for both), so these still fall back to our own build's cfg! values,
+        // which is wrong for a binary moved to or emulating a different MIPS host
+        "mips64" => CPU::MIPS(MIPS::Mips64 {
+            abi: detect_mips64_abi(),
+            endian: detect_endianness(),
+        }),
+        "mips" => CPU::MIPS(MIPS::Mips32 {
+            abi: MipsAbi::O32,
+            endian: detect_endianness(),
+        }),
+        "ppc64le" | "ppc64" | "powerpc" => CPU::PowerPC,
+        "sparc" | "sparc64" => CPU::SPARC,
+        "riscv32" | "riscv64" => CPU::RISCV,
+        "s390x" => CPU::S390X,
+        _ => CPU::Other,
+    }
+}
+
 /// Detects what window system the program is running under, and returns the `WindowSystem` enum
 pub fn detect_windowsystem() -> WindowSystem {
     match std::env::consts::OS {
@@ -245,4 +581,115 @@ mod tests {
 
         println!("{:?}", ws);
     }
+
+    #[test]
+    fn test_detect_bitness_runtime() {
+        let bitness = detect_bitness_runtime();
+
+        println!("{:?}", bitness);
+    }
+
+    #[test]
+    fn test_detect_os_runtime() {
+        let os = detect_os_runtime();
+
+        println!("{:?}", os);
+    }
+
+    #[test]
+    fn test_detect_architecture_runtime() {
+        let cpu = detect_architecture_runtime();
+
+        println!("{:?}", cpu);
+    }
+
+    #[test]
+    fn test_x86_variant_from_processor() {
+        assert!(matches!(
+            x86_variant_from_processor("i586"),
+            Some(X86::I586)
+        ));
+        assert!(x86_variant_from_processor("unknown").is_none());
+    }
+
+    #[test]
+    fn test_detect_endianness() {
+        let endianness = detect_endianness();
+
+        println!("{:?}", endianness);
+    }
+
+    #[test]
+    fn test_mips_predicates() {
+        let mips32 = MIPS::Mips32 {
+            abi: MipsAbi::O32,
+            endian: Endianness::Little,
+        };
+        let mips64_n32 = MIPS::Mips64 {
+            abi: MipsAbi::N32,
+            endian: Endianness::Big,
+        };
+
+        assert!(is_mips32(mips32));
+        assert!(!is_mips64(mips32));
+        assert!(is_mips64(mips64_n32));
+        assert!(is_mips64_n32(mips64_n32));
+    }
+
+    #[test]
+    fn test_detect_libc() {
+        let libc = detect_libc();
+
+        println!("{:?}", libc);
+    }
+
+    #[test]
+    fn test_is_compatible() {
+        assert!(is_compatible(CPU::X86(X86::I386), CPU::X86(X86::AMD64)));
+        assert!(is_compatible(CPU::X86(X86::I386), CPU::X86(X86::I686)));
+        assert!(!is_compatible(CPU::X86(X86::I686), CPU::X86(X86::I386)));
+        assert!(!is_compatible(CPU::X86(X86::AMD64), CPU::X86(X86::I686)));
+
+        assert!(is_compatible(
+            CPU::ARM(ARM::AArch32),
+            CPU::ARM(ARM::AArch64)
+        ));
+        assert!(is_compatible(
+            CPU::ARM(ARM::AArch32),
+            CPU::ARM(ARM::AppleSilicon)
+        ));
+        assert!(!is_compatible(
+            CPU::ARM(ARM::AArch64),
+            CPU::ARM(ARM::AArch32)
+        ));
+
+        let mips32 = MIPS::Mips32 {
+            abi: MipsAbi::O32,
+            endian: Endianness::Little,
+        };
+        let mips64 = MIPS::Mips64 {
+            abi: MipsAbi::N64,
+            endian: Endianness::Little,
+        };
+        assert!(is_compatible(CPU::MIPS(mips32), CPU::MIPS(mips64)));
+        assert!(!is_compatible(CPU::MIPS(mips64), CPU::MIPS(mips32)));
+
+        let mips32_big_endian = MIPS::Mips32 {
+            abi: MipsAbi::O32,
+            endian: Endianness::Big,
+        };
+        assert!(!is_compatible(
+            CPU::MIPS(mips32),
+            CPU::MIPS(mips32_big_endian)
+        ));
+
+        assert!(!is_compatible(CPU::ARM(ARM::AArch32), CPU::X86(X86::AMD64)));
+    }
+
+    #[test]
+    fn test_reconcile_bitness() {
+        let cpu = reconcile_bitness(detect_architecture());
+
+        println!("{:?}", cpu);
+    }
 }
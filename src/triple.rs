@@ -0,0 +1,236 @@
+//! Renders and parses LLVM/Rust-style target triples (e.g. `x86_64-unknown-linux-gnu`,
+//! `aarch64-apple-darwin`) to and from the `CPU` / `OperatingSystem` enums, so callers have
+//! a stable string form for things like logging, cache keys, and cross-compilation tooling
+//! instead of having to match on the nested enums themselves.
+
+use crate::{
+    detect_endianness, DarwinKernel, LinuxKernel, MipsAbi, NTKernel, OperatingSystem, UnixLike,
+    WindowsAbi, ARM, CPU, MIPS, X86,
+};
+
+/// Something went wrong trying to parse a target triple string
+#[derive(Clone, Debug)]
+pub enum TripleError {
+    /// The triple didn't have 2, 3, or 4 `-`-separated fields
+    Malformed(String),
+    /// The arch field wasn't one we recognize
+    UnknownArch(String),
+    /// The os field wasn't one we recognize
+    UnknownOs(String),
+}
+
+fn arch_component(cpu: CPU) -> &'static str {
+    match cpu {
+        CPU::X86(X86::AMD64) => "x86_64",
+        CPU::X86(X86::I386) => "i386",
+        CPU::X86(X86::I486) => "i486",
+        CPU::X86(X86::I586) => "i586",
+        CPU::X86(X86::I686) => "i686",
+        CPU::X86(X86::EightyEightySix) => "8086",
+        CPU::ARM(ARM::AArch32) => "arm",
+        CPU::ARM(ARM::AArch64) => "aarch64",
+        CPU::ARM(ARM::AppleSilicon) => "aarch64",
+        // Linux only ever reports "mips"/"mips64" for std::env::consts::ARCH, so that's
+        // the split we render back out to, even though it loses the MipsI..MipsV distinction
+        CPU::MIPS(MIPS::MipsI)
+        | CPU::MIPS(MIPS::MipsII)
+        | CPU::MIPS(MIPS::MipsIII)
+        | CPU::MIPS(MIPS::Mips32 { .. }) => "mips",
+        CPU::MIPS(MIPS::MipsIV) | CPU::MIPS(MIPS::MipsV) | CPU::MIPS(MIPS::Mips64 { .. }) => {
+            "mips64"
+        }
+        CPU::PowerPC => "powerpc",
+        CPU::SPARC => "sparc",
+        CPU::RISC => "risc",
+        CPU::RISCV => "riscv",
+        CPU::Alpha => "alpha",
+        CPU::IA64 => "ia64",
+        CPU::HPPA => "hppa",
+        CPU::S390 => "s390",
+        CPU::S390X => "s390x",
+        CPU::SuperH => "sh",
+        CPU::SystemZ => "systemz",
+        CPU::XCore => "xcore",
+        CPU::Other => "unknown",
+    }
+}
+
+fn vendor_component(os: OperatingSystem) -> &'static str {
+    match os {
+        OperatingSystem::UnixLike(UnixLike::Darwin(_)) => "apple",
+        OperatingSystem::Windows(_) => "pc",
+        _ => "unknown",
+    }
+}
+
+fn os_component(os: OperatingSystem) -> &'static str {
+    match os {
+        OperatingSystem::UnixLike(UnixLike::Linux(_)) => "linux",
+        OperatingSystem::UnixLike(UnixLike::Darwin(_)) => "darwin",
+        // we only have one `BSD` variant, so "freebsd" is our best generic guess
+        OperatingSystem::UnixLike(UnixLike::BSD) => "freebsd",
+        OperatingSystem::UnixLike(UnixLike::SolarisOrUhOopsIMeanIllumos) => "solaris",
+        OperatingSystem::Windows(_) => "windows",
+        OperatingSystem::DOS => "dos",
+        OperatingSystem::Unknown => "unknown",
+    }
+}
+
+fn abi_component(os: OperatingSystem) -> Option<&'static str> {
+    match os {
+        OperatingSystem::UnixLike(UnixLike::Linux(LinuxKernel::NormalLinuxGnu)) => Some("gnu"),
+        OperatingSystem::UnixLike(UnixLike::Linux(LinuxKernel::NormalLinuxMusl)) => Some("musl"),
+        OperatingSystem::UnixLike(UnixLike::Linux(LinuxKernel::Android)) => Some("android"),
+        OperatingSystem::UnixLike(UnixLike::Linux(LinuxKernel::ChromeOS)) => Some("gnu"),
+        OperatingSystem::Windows(NTKernel::Windows(WindowsAbi::Msvc)) => Some("msvc"),
+        OperatingSystem::Windows(NTKernel::Windows(WindowsAbi::Gnu)) => Some("gnu"),
+        OperatingSystem::Windows(NTKernel::WindowsServer(WindowsAbi::Msvc)) => Some("msvc"),
+        OperatingSystem::Windows(NTKernel::WindowsServer(WindowsAbi::Gnu)) => Some("gnu"),
+        _ => None,
+    }
+}
+
+/// Renders a `CPU` + `OperatingSystem` pair into a canonical target triple string,
+/// e.g. `x86_64-unknown-linux-gnu` or `aarch64-apple-darwin`.
+pub fn render_triple(cpu: CPU, os: OperatingSystem) -> String {
+    let arch = arch_component(cpu);
+    let vendor = vendor_component(os);
+    let os_name = os_component(os);
+
+    match abi_component(os) {
+        Some(abi) => format!("{arch}-{vendor}-{os_name}-{abi}"),
+        None => format!("{arch}-{vendor}-{os_name}"),
+    }
+}
+
+fn parse_arch(arch: &str) -> Result<CPU, TripleError> {
+    match arch {
+        "x86_64" => Ok(CPU::X86(X86::AMD64)),
+        "i386" => Ok(CPU::X86(X86::I386)),
+        "i486" => Ok(CPU::X86(X86::I486)),
+        "i586" => Ok(CPU::X86(X86::I586)),
+        "i686" => Ok(CPU::X86(X86::I686)),
+        "8086" => Ok(CPU::X86(X86::EightyEightySix)),
+        "arm" => Ok(CPU::ARM(ARM::AArch32)),
+        "aarch64" => Ok(CPU::ARM(ARM::AArch64)),
+        // a bare triple string doesn't carry ABI/endianness info, so we fall back to the
+        // current host's endianness and the default ABI for the word size
+        "mips" => Ok(CPU::MIPS(MIPS::Mips32 {
+            abi: MipsAbi::O32,
+            endian: detect_endianness(),
+        })),
+        "mips64" => Ok(CPU::MIPS(MIPS::Mips64 {
+            abi: MipsAbi::N64,
+            endian: detect_endianness(),
+        })),
+        "powerpc" => Ok(CPU::PowerPC),
+        "sparc" => Ok(CPU::SPARC),
+        "risc" => Ok(CPU::RISC),
+        "riscv" => Ok(CPU::RISCV),
+        "alpha" => Ok(CPU::Alpha),
+        "ia64" => Ok(CPU::IA64),
+        "hppa" => Ok(CPU::HPPA),
+        "s390" => Ok(CPU::S390),
+        "s390x" => Ok(CPU::S390X),
+        "sh" => Ok(CPU::SuperH),
+        "systemz" => Ok(CPU::SystemZ),
+        "xcore" => Ok(CPU::XCore),
+        other => Err(TripleError::UnknownArch(other.to_string())),
+    }
+}
+
+fn parse_os(os_name: &str, abi: Option<&str>) -> Result<OperatingSystem, TripleError> {
+    match os_name {
+        "linux" => {
+            let kernel = match abi {
+                Some("musl") => LinuxKernel::NormalLinuxMusl,
+                Some("android") => LinuxKernel::Android,
+                _ => LinuxKernel::NormalLinuxGnu,
+            };
+
+            Ok(OperatingSystem::UnixLike(UnixLike::Linux(kernel)))
+        }
+        "darwin" => Ok(OperatingSystem::UnixLike(UnixLike::Darwin(
+            DarwinKernel::MacOSGreaterThan9,
+        ))),
+        "freebsd" | "netbsd" | "openbsd" | "dragonfly" => {
+            Ok(OperatingSystem::UnixLike(UnixLike::BSD))
+        }
+        "solaris" | "illumos" => Ok(OperatingSystem::UnixLike(
+            UnixLike::SolarisOrUhOopsIMeanIllumos,
+        )),
+        "windows" => {
+            let windows_abi = match abi {
+                Some("gnu") => WindowsAbi::Gnu,
+                _ => WindowsAbi::Msvc,
+            };
+
+            Ok(OperatingSystem::Windows(NTKernel::Windows(windows_abi)))
+        }
+        "dos" => Ok(OperatingSystem::DOS),
+        other => Err(TripleError::UnknownOs(other.to_string())),
+    }
+}
+
+/// Parses a target triple string back into a `(CPU, OperatingSystem)` pair.
+/// Tolerates the 2-field (`arch-os`), 3-field (`arch-vendor-os`), and 4-field
+/// (`arch-vendor-os-abi`) forms, since not every triple in the wild bothers with a vendor.
+pub fn parse_triple(triple: &str) -> Result<(CPU, OperatingSystem), TripleError> {
+    let fields: Vec<&str> = triple.split('-').collect();
+
+    let (arch, os_name, abi) = match fields.as_slice() {
+        [arch, os_name] => (*arch, *os_name, None),
+        [arch, _vendor, os_name] => (*arch, *os_name, None),
+        [arch, _vendor, os_name, abi] => (*arch, *os_name, Some(*abi)),
+        _ => return Err(TripleError::Malformed(triple.to_string())),
+    };
+
+    let cpu = parse_arch(arch)?;
+    let os = parse_os(os_name, abi)?;
+
+    Ok((cpu, os))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_triple() {
+        let triple = render_triple(
+            CPU::X86(X86::AMD64),
+            OperatingSystem::UnixLike(UnixLike::Linux(LinuxKernel::NormalLinuxGnu)),
+        );
+
+        assert_eq!(triple, "x86_64-unknown-linux-gnu");
+    }
+
+    #[test]
+    fn test_render_triple_darwin() {
+        let triple = render_triple(
+            CPU::ARM(ARM::AppleSilicon),
+            OperatingSystem::UnixLike(UnixLike::Darwin(DarwinKernel::MacOSGreaterThan9)),
+        );
+
+        assert_eq!(triple, "aarch64-apple-darwin");
+    }
+
+    #[test]
+    fn test_parse_triple_roundtrip() {
+        let (cpu, os) = parse_triple("x86_64-unknown-linux-gnu").unwrap();
+
+        println!("{:?} {:?}", cpu, os);
+
+        let triple = render_triple(cpu, os);
+
+        assert_eq!(triple, "x86_64-unknown-linux-gnu");
+    }
+
+    #[test]
+    fn test_windows_gnu_roundtrip() {
+        let (cpu, os) = parse_triple("x86_64-pc-windows-gnu").unwrap();
+        let triple = render_triple(cpu, os);
+
+        assert_eq!(triple, "x86_64-pc-windows-gnu");
+    }
+}